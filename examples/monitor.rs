@@ -10,12 +10,12 @@ async fn main() -> Result<()> {
 
     let mut s = pin!(c.handle.addresses.stream());
     while let Some(addr) = s.next().await {
-        println!("current: {:?}", addr);
+        println!("current: {}", addr);
     }
 
     let mut s = pin!(c.handle.monitor.stream());
     while let Some(item) = s.next().await {
-        println!("monitor: {:?}", item);
+        println!("monitor: {}", item);
     }
 
     Err(Error::from(ErrorKind::ConnectionAborted))
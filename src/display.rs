@@ -0,0 +1,165 @@
+use crate::{Address, Message};
+use netlink_packet_route::constants::{
+    IFA_F_SECONDARY, RT_SCOPE_HOST, RT_SCOPE_LINK, RT_SCOPE_NOWHERE, RT_SCOPE_SITE,
+    RT_SCOPE_UNIVERSE,
+};
+use std::fmt::{self, Display, Formatter};
+use std::io::{Error, ErrorKind, Result};
+use std::str::FromStr;
+
+const SCOPES: [(u8, &str); 5] = [
+    (RT_SCOPE_UNIVERSE, "global"),
+    (RT_SCOPE_SITE, "site"),
+    (RT_SCOPE_LINK, "link"),
+    (RT_SCOPE_HOST, "host"),
+    (RT_SCOPE_NOWHERE, "nowhere"),
+];
+
+/// Formats like `ip address`, e.g. `192.168.1.5/24 dev wlan0 scope global`.
+impl Display for Address {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)?;
+        match self.if_name() {
+            Some(name) => write!(f, " dev {}", name)?,
+            None => write!(f, " dev if{}", self.index)?,
+        }
+        match SCOPES.iter().find(|(s, _)| *s == self.scope) {
+            Some((_, name)) => write!(f, " scope {}", name)?,
+            None => write!(f, " scope {}", self.scope)?,
+        }
+        if self.is_secondary() {
+            write!(f, " secondary")?;
+        }
+        match self.label() {
+            Some(label) if self.if_name() != Some(label) => write!(f, " label {}", label),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Formats like `ip monitor address`, prefixing deleted addresses with
+/// `Deleted`.
+impl Display for Message {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if !self.is_new() {
+            write!(f, "Deleted ")?;
+        }
+        self.addr.fmt(f)
+    }
+}
+
+fn invalid(s: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, format!("invalid address: {:?}", s))
+}
+
+/// Parses the format of [`Display`], where all but the address is optional,
+/// e.g. `10.0.0.2`, `10.0.0.2/8 dev eth0`. The interface index is left as 0
+/// unless given as `dev if<index>`, so a parsed address only equals a
+/// retrieved one in that form.
+impl FromStr for Address {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Address> {
+        let mut words = s.split_whitespace();
+        let net = words.next().ok_or_else(|| invalid(s))?;
+        let (addr, prefix_len) = match net.split_once('/') {
+            Some((addr, len)) => (addr, len.parse().map_err(|_| invalid(s))?),
+            None => (net, 32),
+        };
+        if prefix_len > 32 {
+            return Err(invalid(s));
+        }
+        let addr = addr.parse().map_err(|_| invalid(s))?;
+        let mut r = Address::new(addr, prefix_len, None, 0);
+        while let Some(word) = words.next() {
+            match word {
+                "dev" => {
+                    let name = words.next().ok_or_else(|| invalid(s))?;
+                    match name.strip_prefix("if").and_then(|i| i.parse().ok()) {
+                        Some(index) => r.index = index,
+                        None => r.if_name = Some(name.to_owned()),
+                    }
+                }
+                "scope" => {
+                    let scope = words.next().ok_or_else(|| invalid(s))?;
+                    r.scope = match SCOPES.iter().find(|(_, name)| *name == scope) {
+                        Some((s, _)) => *s,
+                        None => scope.parse().map_err(|_| invalid(s))?,
+                    };
+                }
+                "secondary" => r.flags |= IFA_F_SECONDARY,
+                "label" => r.label = Some(words.next().ok_or_else(|| invalid(s))?.to_owned()),
+                _ => return Err(invalid(s)),
+            }
+        }
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Address, Message};
+    use netlink_packet_route::constants::RT_SCOPE_LINK;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn display() {
+        let mut a = Address::new(Ipv4Addr::new(192, 168, 1, 5), 24, None, 3);
+        assert_eq!(a.to_string(), "192.168.1.5/24 dev if3 scope global");
+        a.label = Some("wlan0:1".into());
+        assert_eq!(
+            a.to_string(),
+            "192.168.1.5/24 dev if3 scope global label wlan0:1"
+        );
+        a.if_name = Some("wlan0".into());
+        a.label = Some("wlan0".into());
+        assert_eq!(a.to_string(), "192.168.1.5/24 dev wlan0 scope global");
+        assert_eq!(
+            Message::new(a.clone(), false).to_string(),
+            "Deleted 192.168.1.5/24 dev wlan0 scope global"
+        );
+        a.label = Some("wlan0:1".into());
+        a.scope = RT_SCOPE_LINK;
+        assert_eq!(
+            a.to_string(),
+            "192.168.1.5/24 dev wlan0 scope link label wlan0:1"
+        );
+    }
+
+    #[test]
+    fn parse() {
+        let s = "10.0.0.2/8 dev eth0 scope link secondary label eth0:1";
+        let a: Address = s.parse().unwrap();
+        assert_eq!(a.addr(), &Ipv4Addr::new(10, 0, 0, 2));
+        assert_eq!(a.prefix_len(), 8);
+        assert_eq!(a.if_name(), Some("eth0"));
+        assert_eq!(a.label(), Some("eth0:1"));
+        assert_eq!(a.scope(), RT_SCOPE_LINK);
+        assert!(a.is_secondary());
+        assert_eq!(a.to_string(), s);
+
+        let a: Address = "10.0.0.2".parse().unwrap();
+        assert_eq!(a.prefix_len(), 32);
+        assert!(a.is_global());
+        assert_eq!("10.0.0.2/8 dev if2".parse::<Address>().unwrap().index(), 2);
+        let a: Address = "10.0.0.2/8 dev eth0".parse().unwrap();
+        assert_eq!((a.index(), a.label()), (0, None));
+
+        let mut a = Address::new(Ipv4Addr::new(10, 0, 0, 2), 8, Some("eth0:1".into()), 2);
+        let b: Address = a.to_string().parse().unwrap();
+        assert_eq!(b, a);
+        assert_eq!(b.label(), Some("eth0:1"));
+        a.if_name = Some("eth0".into());
+        assert_ne!(a.to_string().parse::<Address>().unwrap(), a);
+
+        for s in [
+            "",
+            "10.0.0.2/33",
+            "10.0.0.256",
+            "10.0.0.2 dev",
+            "10.0.0.2 up",
+        ] {
+            assert!(s.parse::<Address>().is_err(), "{}", s);
+        }
+    }
+}
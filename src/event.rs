@@ -0,0 +1,408 @@
+use crate::Message;
+use ipnet::IpNet;
+use netlink_packet_route::{
+    constants::RT_TABLE_MAIN,
+    rtnl::{
+        link::nlas::Nla as LinkNla, neighbour::nlas::Nla as NeighNla, route::nlas::Nla as RouteNla,
+    },
+    LinkMessage, NeighbourMessage, RouteMessage,
+};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// An event from the monitor.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Event {
+    /// An address is added or deleted.
+    Addr(Message),
+    /// A link is added, changed or deleted.
+    Link(LinkEvent),
+    /// A route is added or deleted.
+    Route(RouteEvent),
+    /// A neighbour entry is added, changed or deleted.
+    Neigh(NeighEvent),
+    /// The socket buffer overran and some messages were dropped, so the
+    /// current addresses should be retrieved again.
+    Resync,
+}
+
+impl Event {
+    /// Gets the name of the interface the event concerns, if any.
+    pub fn if_name(&self) -> Option<&str> {
+        match self {
+            Event::Addr(msg) => msg.addr().if_name(),
+            Event::Link(link) => link.name(),
+            Event::Route(route) => route.if_name(),
+            Event::Neigh(neigh) => neigh.if_name(),
+            Event::Resync => None,
+        }
+    }
+}
+
+/// A property change of a link, along with the previous value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LinkChange {
+    /// The interface is renamed.
+    Renamed(Option<String>),
+    /// The MTU is changed.
+    Mtu(Option<u32>),
+    /// The hardware address is changed.
+    Mac(Option<Vec<u8>>),
+    /// The `IFF_*` flags are changed, e.g. the interface went up or down.
+    Flags(u32),
+}
+
+/// A link event, denoting a new, changed or deleted interface.
+#[derive(Debug, Clone)]
+pub struct LinkEvent {
+    index: u32,
+    name: Option<String>,
+    flags: u32,
+    mtu: Option<u32>,
+    mac: Option<Vec<u8>>,
+    new: bool,
+    changes: Vec<LinkChange>,
+}
+
+impl LinkEvent {
+    pub(crate) fn new(lm: &LinkMessage, new: bool) -> Self {
+        let mut r = LinkEvent {
+            index: lm.header.index,
+            name: None,
+            flags: lm.header.flags,
+            mtu: None,
+            mac: None,
+            new,
+            changes: Vec::new(),
+        };
+        for nla in &lm.nlas {
+            match nla {
+                LinkNla::IfName(name) => r.name = Some(name.clone()),
+                LinkNla::Mtu(mtu) => r.mtu = Some(*mtu),
+                LinkNla::Address(mac) => r.mac = Some(mac.clone()),
+                _ => {}
+            }
+        }
+        r
+    }
+
+    /// Gets the index of the interface.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Gets the name of the interface.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Gets the `IFF_*` flags of the interface.
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Gets the MTU of the interface.
+    pub fn mtu(&self) -> Option<u32> {
+        self.mtu
+    }
+
+    /// Gets the hardware address of the interface.
+    pub fn mac(&self) -> Option<&[u8]> {
+        self.mac.as_deref()
+    }
+
+    /// Checks whether the interface is new or changed, rather than deleted.
+    pub fn is_new(&self) -> bool {
+        self.new
+    }
+
+    /// Gets the changes of the properties since the previous state of the
+    /// interface, which is known for all interfaces present when the
+    /// connection is created. This is empty for deleted interfaces, and for
+    /// interfaces first seen in this event, e.g. newly created ones.
+    pub fn changes(&self) -> &[LinkChange] {
+        &self.changes
+    }
+
+    pub(crate) fn set_previous(&mut self, old: &LinkEvent) {
+        if !self.new {
+            return;
+        }
+        self.changes.clear();
+        if self.name != old.name {
+            self.changes.push(LinkChange::Renamed(old.name.clone()));
+        }
+        if self.mtu != old.mtu {
+            self.changes.push(LinkChange::Mtu(old.mtu));
+        }
+        if self.mac != old.mac {
+            self.changes.push(LinkChange::Mac(old.mac.clone()));
+        }
+        if self.flags != old.flags {
+            self.changes.push(LinkChange::Flags(old.flags));
+        }
+    }
+}
+
+/// A route event, denoting a new or deleted route.
+#[derive(Debug, Clone)]
+pub struct RouteEvent {
+    destination: IpNet,
+    gateway: Option<IpAddr>,
+    oif: Option<u32>,
+    pub(crate) if_name: Option<String>,
+    table: u32,
+    priority: u32,
+    kind: u8,
+    new: bool,
+}
+
+impl RouteEvent {
+    pub(crate) fn new(rm: &RouteMessage, new: bool) -> Self {
+        let destination = match rm.destination_prefix() {
+            Some((addr, len)) => IpNet::new(addr, len).ok(),
+            None => None,
+        };
+        let destination = destination.unwrap_or_else(|| {
+            let any = if rm.header.address_family == libc::AF_INET6 as u8 {
+                IpAddr::V6(Ipv6Addr::UNSPECIFIED)
+            } else {
+                IpAddr::V4(Ipv4Addr::UNSPECIFIED)
+            };
+            IpNet::new(any, 0).unwrap()
+        });
+        // `RTA_TABLE` carries table IDs that don't fit in the header.
+        let table = rm
+            .nlas
+            .iter()
+            .find_map(|nla| match nla {
+                RouteNla::Table(t) => Some(*t),
+                _ => None,
+            })
+            .unwrap_or(rm.header.table as u32);
+        let priority = rm
+            .nlas
+            .iter()
+            .find_map(|nla| match nla {
+                RouteNla::Priority(p) => Some(*p),
+                _ => None,
+            })
+            .unwrap_or(0);
+        RouteEvent {
+            destination,
+            gateway: rm.gateway(),
+            oif: rm.output_interface(),
+            if_name: None,
+            table,
+            priority,
+            kind: rm.header.kind,
+            new,
+        }
+    }
+
+    /// Gets the destination subnet.
+    pub fn destination(&self) -> &IpNet {
+        &self.destination
+    }
+
+    /// Gets the gateway of the route.
+    pub fn gateway(&self) -> Option<&IpAddr> {
+        self.gateway.as_ref()
+    }
+
+    /// Gets the index of the output interface.
+    pub fn oif(&self) -> Option<u32> {
+        self.oif
+    }
+
+    /// Gets the name of the output interface, resolved from its index.
+    pub fn if_name(&self) -> Option<&str> {
+        self.if_name.as_deref()
+    }
+
+    /// Gets the routing table ID, e.g. `RT_TABLE_MAIN`.
+    pub fn table(&self) -> u32 {
+        self.table
+    }
+
+    /// Gets the metric of the route, where lower values are preferred.
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    /// Gets the `RTN_*` type of the route.
+    pub fn kind(&self) -> u8 {
+        self.kind
+    }
+
+    /// Checks whether the route is a default route.
+    pub fn is_default(&self) -> bool {
+        self.destination.prefix_len() == 0
+    }
+
+    /// Checks whether the route is a default route via a gateway in the main
+    /// routing table.
+    pub fn is_default_gateway(&self) -> bool {
+        self.is_default() && self.gateway.is_some() && self.table == RT_TABLE_MAIN as u32
+    }
+
+    /// Checks whether the route is new or deleted.
+    pub fn is_new(&self) -> bool {
+        self.new
+    }
+}
+
+/// A neighbour event, denoting a new, changed or deleted entry in the ARP or
+/// NDP cache.
+#[derive(Debug, Clone)]
+pub struct NeighEvent {
+    index: u32,
+    pub(crate) if_name: Option<String>,
+    destination: Option<IpAddr>,
+    mac: Option<Vec<u8>>,
+    state: u16,
+    new: bool,
+}
+
+impl NeighEvent {
+    pub(crate) fn new(nm: &NeighbourMessage, new: bool) -> Self {
+        let mut r = NeighEvent {
+            index: nm.header.ifindex,
+            if_name: None,
+            destination: None,
+            mac: None,
+            state: nm.header.state,
+            new,
+        };
+        for nla in &nm.nlas {
+            match nla {
+                NeighNla::Destination(a) => r.destination = ip(a),
+                NeighNla::LinkLocalAddress(mac) => r.mac = Some(mac.clone()),
+                _ => {}
+            }
+        }
+        r
+    }
+
+    /// Gets the index of the interface.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Gets the name of the interface, resolved from its index.
+    pub fn if_name(&self) -> Option<&str> {
+        self.if_name.as_deref()
+    }
+
+    /// Gets the IP address of the neighbour.
+    pub fn destination(&self) -> Option<&IpAddr> {
+        self.destination.as_ref()
+    }
+
+    /// Gets the hardware address of the neighbour.
+    pub fn mac(&self) -> Option<&[u8]> {
+        self.mac.as_deref()
+    }
+
+    /// Gets the `NUD_*` state of the entry, e.g. `NUD_REACHABLE`.
+    pub fn state(&self) -> u16 {
+        self.state
+    }
+
+    /// Checks whether the entry is new or changed, rather than deleted.
+    pub fn is_new(&self) -> bool {
+        self.new
+    }
+}
+
+fn ip(a: &[u8]) -> Option<IpAddr> {
+    if let Ok(c) = <[u8; 4]>::try_from(a) {
+        Some(IpAddr::from(c))
+    } else if let Ok(c) = <[u8; 16]>::try_from(a) {
+        Some(IpAddr::from(c))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LinkChange, LinkEvent, NeighEvent, RouteEvent};
+    use netlink_packet_route::{
+        constants::NUD_REACHABLE,
+        rtnl::{link::nlas::Nla as LinkNla, neighbour::nlas::Nla as NeighNla, route::nlas::Nla},
+        LinkMessage, NeighbourMessage, RouteMessage,
+    };
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn link_changes() {
+        let mut lm = LinkMessage::default();
+        lm.header.index = 3;
+        lm.nlas.push(LinkNla::IfName("eth0".into()));
+        lm.nlas.push(LinkNla::Mtu(1500));
+        let old = LinkEvent::new(&lm, true);
+        assert!(old.changes().is_empty());
+
+        lm.nlas = vec![LinkNla::IfName("lan0".into()), LinkNla::Mtu(1400)];
+        let mut new = LinkEvent::new(&lm, true);
+        new.set_previous(&old);
+        assert_eq!(
+            new.changes(),
+            [
+                LinkChange::Renamed(Some("eth0".into())),
+                LinkChange::Mtu(Some(1500))
+            ]
+        );
+
+        let mut del = LinkEvent::new(&lm, false);
+        del.set_previous(&new);
+        assert!(del.changes().is_empty());
+    }
+
+    #[test]
+    fn default_route() {
+        let mut rm = RouteMessage::default();
+        rm.header.address_family = libc::AF_INET as u8;
+        rm.header.table = 254;
+        rm.nlas.push(Nla::Gateway(vec![192, 168, 1, 1]));
+        rm.nlas.push(Nla::Oif(2));
+        let r = RouteEvent::new(&rm, true);
+        assert!(r.is_default() && r.is_default_gateway() && r.is_new());
+        assert_eq!(r.destination().to_string(), "0.0.0.0/0");
+        assert_eq!(
+            r.gateway(),
+            Some(&IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)))
+        );
+        assert_eq!(r.oif(), Some(2));
+        assert_eq!(r.table(), 254);
+
+        rm.header.destination_prefix_length = 24;
+        rm.nlas.push(Nla::Destination(vec![10, 0, 0, 0]));
+        rm.nlas.push(Nla::Table(1000));
+        let r = RouteEvent::new(&rm, false);
+        assert!(!r.is_default());
+        assert_eq!(r.destination().to_string(), "10.0.0.0/24");
+        assert_eq!(r.table(), 1000);
+    }
+
+    #[test]
+    fn neighbour() {
+        let mut nm = NeighbourMessage::default();
+        nm.header.ifindex = 2;
+        nm.header.state = NUD_REACHABLE;
+        nm.nlas.push(NeighNla::Destination(vec![192, 168, 1, 1]));
+        nm.nlas
+            .push(NeighNla::LinkLocalAddress(vec![2, 0, 0, 0, 0, 1]));
+        let n = NeighEvent::new(&nm, true);
+        assert_eq!(n.index(), 2);
+        assert_eq!(
+            n.destination(),
+            Some(&IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)))
+        );
+        assert_eq!(n.mac(), Some(&[2, 0, 0, 0, 0, 1][..]));
+        assert_eq!(n.state(), NUD_REACHABLE);
+        assert!(n.is_new());
+    }
+}
@@ -0,0 +1,83 @@
+use futures::{
+    future::{select, Either},
+    stream, Stream, StreamExt,
+};
+use futures_timer::Delay;
+use std::io::{Error, ErrorKind, Result};
+use std::time::Duration;
+
+/// Groups the items into batches, each closed when it has `max_items` items or
+/// `max_delay` has passed since its first item.
+pub(crate) fn batched<S: Stream>(
+    s: S,
+    max_delay: Duration,
+    max_items: usize,
+) -> impl Stream<Item = Vec<S::Item>> {
+    stream::unfold(Some(Box::pin(s)), move |s| async move {
+        let mut s = s?;
+        let mut batch = vec![s.next().await?];
+        let mut delay = Delay::new(max_delay);
+        while batch.len() < max_items {
+            match select(s.next(), &mut delay).await {
+                Either::Left((Some(item), _)) => batch.push(item),
+                Either::Left((None, _)) => return Some((batch, None)),
+                Either::Right(_) => break,
+            }
+        }
+        Some((batch, Some(s)))
+    })
+}
+
+/// Yields a `TimedOut` error whenever no item arrives for `idle`, without
+/// ending the stream.
+pub(crate) fn idle_timeout<S, T>(s: S, idle: Duration) -> impl Stream<Item = Result<T>>
+where
+    S: Stream<Item = Result<T>>,
+{
+    stream::unfold(Box::pin(s), move |mut s| async move {
+        let item = match select(s.next(), Delay::new(idle)).await {
+            Either::Left((item, _)) => item?,
+            Either::Right(_) => Err(Error::from(ErrorKind::TimedOut)),
+        };
+        Some((item, s))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{batched, idle_timeout};
+    use futures::{stream, StreamExt};
+    use std::io::ErrorKind;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn batches() {
+        let s = stream::iter(0..5);
+        let r: Vec<_> = batched(s, Duration::from_secs(10), 2).collect().await;
+        assert_eq!(r, [vec![0, 1], vec![2, 3], vec![4]]);
+
+        let s = stream::iter(0..3).chain(stream::pending());
+        let r: Vec<_> = batched(s, Duration::from_millis(50), 10)
+            .take(1)
+            .collect()
+            .await;
+        assert_eq!(r, [vec![0, 1, 2]]);
+    }
+
+    #[tokio::test]
+    async fn idle() {
+        let s = stream::iter([Ok(1), Ok(2)]).chain(stream::pending());
+        let r: Vec<_> = idle_timeout(s, Duration::from_millis(50))
+            .take(4)
+            .collect()
+            .await;
+        assert_eq!(r[0].as_ref().unwrap(), &1);
+        assert_eq!(r[1].as_ref().unwrap(), &2);
+        assert_eq!(r[2].as_ref().unwrap_err().kind(), ErrorKind::TimedOut);
+        assert_eq!(r[3].as_ref().unwrap_err().kind(), ErrorKind::TimedOut);
+
+        let s = stream::iter([Ok::<_, std::io::Error>(1)]);
+        let r: Vec<_> = idle_timeout(s, Duration::from_secs(10)).collect().await;
+        assert_eq!(r.len(), 1);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::Address;
+
+/// A pattern matching interface names, where `*` matches any sequence of
+/// characters and `?` matches a single one, e.g. `enx*` or `wlp?s0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IfacePattern {
+    pattern: String,
+}
+
+impl IfacePattern {
+    /// Creates a pattern. A pattern without wildcards matches exactly.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        IfacePattern {
+            pattern: pattern.into(),
+        }
+    }
+
+    /// Gets the pattern string.
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Checks whether the interface name matches the pattern.
+    pub fn matches(&self, name: &str) -> bool {
+        let p: Vec<char> = self.pattern.chars().collect();
+        let s: Vec<char> = name.chars().collect();
+        let (mut i, mut j) = (0, 0);
+        let mut star = None;
+        while j < s.len() {
+            if i < p.len() && (p[i] == '?' || p[i] == s[j]) {
+                i += 1;
+                j += 1;
+            } else if i < p.len() && p[i] == '*' {
+                star = Some((i, j));
+                i += 1;
+            } else if let Some((si, sj)) = star {
+                i = si + 1;
+                j = sj + 1;
+                star = Some((si, sj + 1));
+            } else {
+                return false;
+            }
+        }
+        p[i..].iter().all(|&c| c == '*')
+    }
+}
+
+impl From<&str> for IfacePattern {
+    fn from(pattern: &str) -> Self {
+        IfacePattern::new(pattern)
+    }
+}
+
+impl From<String> for IfacePattern {
+    fn from(pattern: String) -> Self {
+        IfacePattern::new(pattern)
+    }
+}
+
+/// A filter of addresses, built by chaining conditions that all have to hold.
+///
+/// ```
+/// use ip_roam::MonitorFilter;
+///
+/// let filter = MonitorFilter::new()
+///     .iface("wlan0")
+///     .iface("enx*")
+///     .scope_global()
+///     .exclude_link_local();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MonitorFilter {
+    ifaces: Vec<IfacePattern>,
+    scope_global: bool,
+    exclude_link_local: bool,
+    exclude_secondary: bool,
+}
+
+impl MonitorFilter {
+    /// Creates a filter matching all addresses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only matches addresses on interfaces matching the pattern. If given
+    /// multiple times, any of the patterns may match.
+    pub fn iface(mut self, pattern: impl Into<IfacePattern>) -> Self {
+        self.ifaces.push(pattern.into());
+        self
+    }
+
+    /// Only matches addresses with the global scope.
+    pub fn scope_global(mut self) -> Self {
+        self.scope_global = true;
+        self
+    }
+
+    /// Excludes link-local addresses in `169.254.0.0/16`.
+    pub fn exclude_link_local(mut self) -> Self {
+        self.exclude_link_local = true;
+        self
+    }
+
+    /// Excludes secondary addresses, leaving the primary address of each
+    /// subnet on an interface.
+    pub fn exclude_secondary(mut self) -> Self {
+        self.exclude_secondary = true;
+        self
+    }
+
+    /// Checks whether the address passes the filter.
+    pub fn matches(&self, addr: &Address) -> bool {
+        (self.ifaces.is_empty() || self.ifaces.iter().any(|p| addr.matches(p)))
+            && (!self.scope_global || addr.is_global())
+            && (!self.exclude_link_local || !addr.addr().is_link_local())
+            && (!self.exclude_secondary || !addr.is_secondary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IfacePattern, MonitorFilter};
+    use crate::Address;
+    use netlink_packet_route::constants::{IFA_F_SECONDARY, RT_SCOPE_HOST};
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn iface_pattern() {
+        let p = IfacePattern::new("enx*");
+        assert!(p.matches("enx00e04c680001"));
+        assert!(p.matches("enx"));
+        assert!(!p.matches("eth0"));
+        let p = IfacePattern::new("wlp?s*0");
+        assert!(p.matches("wlp3s0"));
+        assert!(p.matches("wlp0s20f0"));
+        assert!(!p.matches("wlp0s1"));
+        assert!(!p.matches("wlan0"));
+        assert!(IfacePattern::new("lo").matches("lo"));
+        assert!(!IfacePattern::new("lo").matches("lo0"));
+        assert!(IfacePattern::new("*").matches(""));
+    }
+
+    #[test]
+    fn filter() {
+        let mut a = Address::new(Ipv4Addr::new(192, 168, 1, 2), 24, None, 2);
+        a.if_name = Some("wlan0".into());
+        assert!(MonitorFilter::new().matches(&a));
+        let f = MonitorFilter::new()
+            .iface("eth0")
+            .iface("wl*")
+            .scope_global();
+        assert!(f.matches(&a));
+        assert!(!MonitorFilter::new().iface("eth0").matches(&a));
+
+        a.scope = RT_SCOPE_HOST;
+        assert!(!f.matches(&a));
+
+        let mut b = Address::new(Ipv4Addr::new(169, 254, 3, 4), 16, None, 2);
+        b.if_name = Some("wlan0".into());
+        assert!(f.matches(&b));
+        assert!(!f.clone().exclude_link_local().matches(&b));
+
+        b.flags = IFA_F_SECONDARY;
+        assert!(b.is_secondary());
+        assert!(f.matches(&b));
+        assert!(!f.exclude_secondary().matches(&b));
+    }
+}
@@ -0,0 +1,17 @@
+//! The constants returned by the getters, e.g. [`Address::scope`] and
+//! [`LinkEvent::flags`], for comparing them without depending on
+//! `netlink-packet-route`.
+//!
+//! [`Address::scope`]: crate::Address::scope
+//! [`LinkEvent::flags`]: crate::LinkEvent::flags
+
+pub use netlink_packet_route::constants::{
+    IFA_F_DADFAILED, IFA_F_DEPRECATED, IFA_F_HOMEADDRESS, IFA_F_MANAGETEMPADDR, IFA_F_MCAUTOJOIN,
+    IFA_F_NODAD, IFA_F_NOPREFIXROUTE, IFA_F_OPTIMISTIC, IFA_F_PERMANENT, IFA_F_SECONDARY,
+    IFA_F_STABLE_PRIVACY, IFA_F_TEMPORARY, IFA_F_TENTATIVE, IFF_ALLMULTI, IFF_BROADCAST,
+    IFF_DORMANT, IFF_LOOPBACK, IFF_LOWER_UP, IFF_MULTICAST, IFF_NOARP, IFF_POINTOPOINT,
+    IFF_PROMISC, IFF_RUNNING, IFF_UP, NUD_DELAY, NUD_FAILED, NUD_INCOMPLETE, NUD_NOARP, NUD_NONE,
+    NUD_PERMANENT, NUD_PROBE, NUD_REACHABLE, NUD_STALE, RT_SCOPE_HOST, RT_SCOPE_LINK,
+    RT_SCOPE_NOWHERE, RT_SCOPE_SITE, RT_SCOPE_UNIVERSE, RT_TABLE_DEFAULT, RT_TABLE_LOCAL,
+    RT_TABLE_MAIN,
+};
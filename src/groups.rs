@@ -0,0 +1,27 @@
+//! The `RTMGRP_*` multicast groups, for naming them without depending on
+//! `rtnetlink`. They can be subscribed to with [`Groups::from_bits`], while
+//! the typed ones in [`Groups`] are those yielded as events.
+
+pub use crate::Groups;
+pub use rtnetlink::constants::{
+    RTMGRP_IPV4_IFADDR, RTMGRP_IPV4_MROUTE, RTMGRP_IPV4_ROUTE, RTMGRP_IPV4_RULE,
+    RTMGRP_IPV6_IFADDR, RTMGRP_IPV6_IFINFO, RTMGRP_IPV6_MROUTE, RTMGRP_IPV6_PREFIX,
+    RTMGRP_IPV6_ROUTE, RTMGRP_LINK, RTMGRP_NEIGH, RTMGRP_NOTIFY, RTMGRP_TC,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::{Groups, RTMGRP_IPV4_IFADDR, RTMGRP_LINK, RTMGRP_NEIGH, RTMGRP_TC};
+
+    #[test]
+    fn typed_groups() {
+        assert_eq!(Groups::LINK.bits(), RTMGRP_LINK);
+        assert_eq!(
+            (Groups::IPV4_IFADDR | Groups::NEIGH).bits(),
+            RTMGRP_IPV4_IFADDR | RTMGRP_NEIGH
+        );
+        let groups = Groups::IPV4_IFADDR | Groups::from_bits(RTMGRP_TC);
+        assert!(groups.contains(Groups::from_bits(RTMGRP_TC)));
+        assert_eq!(groups.bits(), RTMGRP_IPV4_IFADDR | RTMGRP_TC);
+    }
+}
@@ -1,37 +1,189 @@
-use futures::channel::mpsc::UnboundedReceiver;
 use futures::{
+    future::{select, Either, FutureExt},
     stream::{StreamExt, TryStreamExt},
     Stream,
 };
+use futures_timer::Delay;
+use ipnet::Ipv4Net;
 use netlink_packet_core::{NetlinkMessage, NetlinkPayload};
 use netlink_packet_route::{
+    constants::{IFA_F_SECONDARY, RT_SCOPE_UNIVERSE},
     rtnl::{address::Nla, RtnlMessage::*},
     AddressMessage, RtnlMessage,
 };
 use netlink_proto::{
-    sys::{AsyncSocket, SocketAddr},
+    sys::{AsyncSocket, SocketAddr, TokioSocket},
     Connection as RtConnection,
 };
-use rtnetlink::{constants::*, new_connection, AddressHandle, Handle as RtHandle};
+use nix::errno::Errno;
+use rtnetlink::{
+    constants::*, new_connection, AddressHandle, Handle as RtHandle, IpVersion, RouteHandle,
+};
+use std::cmp::Ordering;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
 use std::io::{Error, ErrorKind, Result};
 use std::net::Ipv4Addr;
+use std::ops::{BitOr, BitOrAssign};
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+macro_rules! event {
+    ($($t:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::event!($($t)*);
+    };
+}
+
+mod display;
+mod event;
+mod ext;
+mod filter;
+pub mod flags;
+pub mod groups;
+mod links;
+pub mod poll;
+mod queue;
+mod set;
+mod stats;
+
+pub use event::{Event, LinkChange, LinkEvent, NeighEvent, RouteEvent};
+pub use filter::{IfacePattern, MonitorFilter};
+use links::Links;
+use queue::{Item, Receiver, Relay};
+pub use set::{AddressSet, Change};
+pub use stats::Stats;
 
 /// A retrieved address entry.
+///
+/// Addresses are compared, hashed and ordered by their identity, i.e. the
+/// interface index, the address and the prefix length, so an address is the
+/// same one in a set even if e.g. its flags change after a promotion from
+/// secondary.
 #[derive(Debug, Clone)]
 pub struct Address {
     addr: Ipv4Addr,
-    label: String,
+    prefix_len: u8,
+    scope: u8,
+    flags: u32,
+    label: Option<String>,
+    index: u32,
+    if_name: Option<String>,
 }
 
 impl Address {
+    fn new(addr: Ipv4Addr, prefix_len: u8, label: Option<String>, index: u32) -> Self {
+        Address {
+            addr,
+            prefix_len,
+            scope: RT_SCOPE_UNIVERSE,
+            flags: 0,
+            label,
+            index,
+            if_name: None,
+        }
+    }
+
     /// Gets the IPv4 address.
     pub fn addr(&self) -> &Ipv4Addr {
         &self.addr
     }
 
-    /// Gets the label of the interface.
-    pub fn label(&self) -> &str {
-        &self.label
+    /// Gets the prefix length of the address.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Gets the address along with its prefix length.
+    pub fn net(&self) -> Ipv4Net {
+        Ipv4Net::new(self.addr, self.prefix_len).unwrap()
+    }
+
+    /// Gets the scope of the address, e.g. [`flags::RT_SCOPE_UNIVERSE`].
+    pub fn scope(&self) -> u8 {
+        self.scope
+    }
+
+    /// Checks whether the address has the global scope, as opposed to e.g.
+    /// loopback or link-local addresses.
+    pub fn is_global(&self) -> bool {
+        self.scope == RT_SCOPE_UNIVERSE
+    }
+
+    /// Gets the `IFA_F_*` flags of the address.
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Checks whether the address is a secondary one, i.e. another address in
+    /// the same subnet is primary on the interface.
+    pub fn is_secondary(&self) -> bool {
+        self.flags & IFA_F_SECONDARY != 0
+    }
+
+    /// Gets the label of the address, which may be an alias like `eth0:1`.
+    ///
+    /// Some drivers don't report labels, so this can be `None`.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    /// Gets the index of the interface.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Gets the name of the interface, resolved from its index.
+    ///
+    /// Names come from a cache of links shared by the handles of a
+    /// connection, which the [`Driver`] keeps up to date as link changes
+    /// arrive, whether or not the monitor is consumed. So renames are only
+    /// seen while `conn` is running.
+    ///
+    /// This is `None` if the interface is gone before its name could be
+    /// resolved.
+    pub fn if_name(&self) -> Option<&str> {
+        self.if_name.as_deref()
+    }
+
+    /// Checks whether the name or the label of the interface matches the
+    /// pattern.
+    pub fn matches(&self, pattern: &IfacePattern) -> bool {
+        matches!(self.if_name(), Some(name) if pattern.matches(name))
+            || matches!(self.label(), Some(label) if pattern.matches(label))
+    }
+}
+
+impl Address {
+    fn key(&self) -> (u32, Ipv4Addr, u8) {
+        (self.index, self.addr, self.prefix_len)
+    }
+}
+
+impl PartialEq for Address {
+    fn eq(&self, other: &Address) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Address {}
+
+impl Hash for Address {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
+
+impl PartialOrd for Address {
+    fn partial_cmp(&self, other: &Address) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Address {
+    fn cmp(&self, other: &Address) -> Ordering {
+        self.key().cmp(&other.key())
     }
 }
 
@@ -39,48 +191,97 @@ impl TryFrom<AddressMessage> for Address {
     type Error = Error;
 
     fn try_from(am: AddressMessage) -> Result<Address> {
+        let index = am.header.index;
+        let prefix_len = am.header.prefix_len;
         let mut the_addr = None;
+        let mut the_local = None;
         let mut the_label = None;
+        // `IFA_FLAGS` supersedes the 8-bit flags in the header when present.
+        let mut flags = am.header.flags as u32;
         for nla in am.nlas {
             match nla {
-                Nla::Address(a) => {
-                    let c: [u8; 4] = match a.try_into() {
-                        Ok(c) => c,
-                        _ => continue,
-                    };
-                    let addr = Ipv4Addr::from(c);
-                    if let Some(label) = the_label {
-                        return Ok(Address { addr, label });
-                    }
-                    the_addr = Some(addr);
-                }
-                Nla::Label(label) => {
-                    if let Some(addr) = the_addr {
-                        return Ok(Address { addr, label });
-                    }
-                    the_label = Some(label);
-                }
+                Nla::Flags(f) => flags = f,
+                Nla::Address(a) => the_addr = ipv4(a),
+                Nla::Local(a) => the_local = ipv4(a),
+                Nla::Label(label) => the_label = Some(label),
                 _ => {}
             }
         }
-        Err(Error::from(ErrorKind::NotFound))
+        // On point-to-point interfaces, `IFA_ADDRESS` is the peer address
+        // while `IFA_LOCAL` is ours.
+        match the_local.or(the_addr) {
+            Some(_) if prefix_len > 32 => Err(Error::from(ErrorKind::InvalidData)),
+            Some(addr) => {
+                let mut r = Address::new(addr, prefix_len, the_label, index);
+                r.scope = am.header.scope;
+                r.flags = flags;
+                Ok(r)
+            }
+            None => Err(Error::from(ErrorKind::NotFound)),
+        }
     }
 }
 
+fn ipv4(a: Vec<u8>) -> Option<Ipv4Addr> {
+    let c: [u8; 4] = a.try_into().ok()?;
+    Some(Ipv4Addr::from(c))
+}
+
 /// A handle to get current local addresses.
 #[derive(Debug, Clone)]
 pub struct Addresses {
     handle: RtHandle,
+    links: Links,
+    stats: Stats,
 }
 
 impl Addresses {
     /// Streams the current local addresses.
     pub fn stream(self) -> impl Stream<Item = Address> {
+        self.try_stream().filter_map(|item| async { item.ok() })
+    }
+
+    /// Streams the current local addresses, along with the errors that
+    /// occurred while retrieving them, e.g. `PermissionDenied`.
+    ///
+    /// Entries without IPv4 addresses are skipped rather than reported.
+    pub fn try_stream(self) -> impl Stream<Item = Result<Address>> {
+        let links = self.links;
+        let stats = self.stats;
+        let start = Instant::now();
         let inner = AddressHandle::new(self.handle)
             .get()
             .execute()
             .into_stream();
-        inner.filter_map(|item| async move { item.ok().and_then(|am| am.try_into().ok()) })
+        let done = futures::stream::once(async move {
+            stats.on_dump(start.elapsed());
+            None
+        });
+        let inner = inner.map(Some).chain(done);
+        inner.filter_map(move |item| {
+            let links = links.clone();
+            async move {
+                match item?.map(Address::try_from) {
+                    Ok(Ok(addr)) => Some(Ok(links.resolve(addr).await)),
+                    Ok(Err(e)) if e.kind() == ErrorKind::NotFound => None,
+                    Ok(Err(e)) => {
+                        event!(tracing::Level::DEBUG, error = %e, "skipped address");
+                        Some(Err(e))
+                    }
+                    Err(e) => {
+                        event!(tracing::Level::DEBUG, error = %e, "failed to get address");
+                        Some(Err(rt_error(e)))
+                    }
+                }
+            }
+        })
+    }
+}
+
+fn rt_error(e: rtnetlink::Error) -> Error {
+    match e {
+        rtnetlink::Error::NetlinkError(m) => m.to_io(),
+        e => Error::new(ErrorKind::InvalidData, e),
     }
 }
 
@@ -89,11 +290,20 @@ impl Addresses {
 pub struct Message {
     addr: Address,
     new: bool,
+    seq: u32,
+    #[cfg(feature = "raw")]
+    raw: Option<AddressMessage>,
 }
 
 impl Message {
     fn new(addr: Address, new: bool) -> Self {
-        Message { addr, new }
+        Message {
+            addr,
+            new,
+            seq: 0,
+            #[cfg(feature = "raw")]
+            raw: None,
+        }
     }
 
     /// Gets the address.
@@ -105,19 +315,46 @@ impl Message {
     pub fn is_new(&self) -> bool {
         self.new
     }
+
+    /// Gets the netlink sequence number, which is the one of the request that
+    /// caused the change, or 0 if it's not caused by a netlink request.
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// Gets the original netlink message, for attributes not modeled by
+    /// [`Address`]. This is only kept for monitor messages, so it's `None` for
+    /// the current addresses wrapped as messages, e.g. by [`Handle::stream`],
+    /// and for messages not decoded from netlink.
+    #[cfg(feature = "raw")]
+    pub fn raw(&self) -> Option<&AddressMessage> {
+        self.raw.as_ref()
+    }
 }
 
 impl TryFrom<RtnlMessage> for Message {
     type Error = Error;
 
     fn try_from(item: RtnlMessage) -> Result<Message> {
-        Ok(match item {
-            NewAddress(a) => Message::new(a.try_into()?, true),
-            DelAddress(a) => Message::new(a.try_into()?, false),
-            _ => {
-                return Err(Error::from(ErrorKind::InvalidData));
+        let (am, new) = match item {
+            NewAddress(a) => (a, true),
+            DelAddress(a) => (a, false),
+            other => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unexpected message: {:?}", other),
+                ));
             }
-        })
+        };
+        #[cfg(feature = "raw")]
+        let raw = am.clone();
+        #[allow(unused_mut)]
+        let mut msg = Message::new(am.try_into()?, new);
+        #[cfg(feature = "raw")]
+        {
+            msg.raw = Some(raw);
+        }
+        Ok(msg)
     }
 }
 
@@ -125,10 +362,18 @@ impl TryFrom<NetlinkMessage<RtnlMessage>> for Message {
     type Error = Error;
 
     fn try_from(item: NetlinkMessage<RtnlMessage>) -> Result<Message> {
-        if let NetlinkPayload::InnerMessage(m) = item.payload {
-            m.try_into()
-        } else {
-            Err(Error::from(ErrorKind::InvalidData))
+        let seq = item.header.sequence_number;
+        match item.payload {
+            NetlinkPayload::InnerMessage(m) => {
+                let mut msg = Message::try_from(m)?;
+                msg.seq = seq;
+                Ok(msg)
+            }
+            NetlinkPayload::Error(e) => Err(e.to_io()),
+            other => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unexpected payload: {:?}", other),
+            )),
         }
     }
 }
@@ -136,42 +381,460 @@ impl TryFrom<NetlinkMessage<RtnlMessage>> for Message {
 /// A monitor to watch the changes of local addresses.
 #[derive(Debug)]
 pub struct Monitor {
-    messages: UnboundedReceiver<(NetlinkMessage<RtnlMessage>, SocketAddr)>,
+    messages: Receiver,
+    links: Links,
+    stats: Stats,
 }
 
 impl Monitor {
     /// Streams the monitor messages.
+    ///
+    /// Messages dropped by a socket buffer overrun go unnoticed, see
+    /// [`Monitor::events`] to detect them.
     pub fn stream(self) -> impl Stream<Item = Message> {
-        self.messages
-            .filter_map(|item| async { item.0.try_into().ok() })
+        self.try_stream().filter_map(|item| async { item.ok() })
+    }
+
+    /// Streams the monitor events, including [`Event::Resync`] on socket
+    /// buffer overruns.
+    pub fn events(self) -> impl Stream<Item = Event> {
+        self.try_events().filter_map(|item| async { item.ok() })
+    }
+
+    /// Like [`Monitor::events`], along with the errors of netlink messages
+    /// that failed to decode.
+    pub fn try_events(self) -> impl Stream<Item = Result<Event>> {
+        decode(self.messages, self.links, self.stats)
+    }
+
+    /// Streams the monitor messages of addresses matching the filter.
+    pub fn stream_filtered(self, filter: MonitorFilter) -> impl Stream<Item = Message> {
+        self.stream()
+            .filter(move |msg| futures::future::ready(filter.matches(msg.addr())))
+    }
+
+    /// Streams the monitor messages in batches, each yielded once it has
+    /// `max_items` messages or `max_delay` has passed since its first message,
+    /// so bursts of changes can be handled at once.
+    pub fn stream_batched(
+        self,
+        max_delay: Duration,
+        max_items: usize,
+    ) -> impl Stream<Item = Vec<Message>> {
+        ext::batched(self.stream(), max_delay, max_items)
+    }
+
+    /// Streams the monitor events of all the subscribed groups, along with the
+    /// decode errors and a `TimedOut` error whenever no event arrives for
+    /// `idle`, e.g. to feed a watchdog. The stream goes on after the errors.
+    pub fn stream_with_idle_timeout(self, idle: Duration) -> impl Stream<Item = Result<Event>> {
+        ext::idle_timeout(self.try_events(), idle)
     }
+
+    /// Streams the changes of default gateways in the main routing table.
+    ///
+    /// The connection must be created with [`Groups::IPV4_ROUTE`] or
+    /// [`Groups::IPV6_ROUTE`] for the changes to be received.
+    pub fn default_gateways(self) -> impl Stream<Item = RouteEvent> {
+        self.events().filter_map(|event| async {
+            match event {
+                Event::Route(route) if route.is_default_gateway() => Some(route),
+                _ => None,
+            }
+        })
+    }
+
+    /// Streams the monitor messages, along with the errors of netlink messages
+    /// that failed to decode into a [`Message`].
+    ///
+    /// Socket buffer overruns are reported as `ENOBUFS`.
+    pub fn try_stream(self) -> impl Stream<Item = Result<Message>> {
+        decode(self.messages, self.links, self.stats).filter_map(|item| async {
+            match item {
+                Ok(Event::Addr(msg)) => Some(Ok(msg)),
+                Ok(Event::Resync) => Some(Err(Error::from_raw_os_error(Errno::ENOBUFS as i32))),
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            }
+        })
+    }
+}
+
+fn decode<S>(messages: S, links: Links, stats: Stats) -> impl Stream<Item = Result<Event>>
+where
+    S: Stream<Item = Item>,
+{
+    messages.filter_map(move |item| {
+        let links = links.clone();
+        let stats = stats.clone();
+        async move {
+            let msg = match item {
+                Item::Message(msg) => msg,
+                Item::Link(link) => return Some(Ok(Event::Link(link))),
+                Item::Overrun => {
+                    event!(tracing::Level::WARN, "socket buffer overrun");
+                    return Some(Ok(Event::Resync));
+                }
+            };
+            match &msg.payload {
+                NetlinkPayload::InnerMessage(m @ (NewRoute(rm) | DelRoute(rm))) => {
+                    let route = RouteEvent::new(rm, matches!(m, NewRoute(_)));
+                    return Some(Ok(Event::Route(links.resolve_route(route).await)));
+                }
+                NetlinkPayload::InnerMessage(m @ (NewNeighbour(nm) | DelNeighbour(nm))) => {
+                    let neigh = NeighEvent::new(nm, matches!(m, NewNeighbour(_)));
+                    return Some(Ok(Event::Neigh(links.resolve_neigh(neigh).await)));
+                }
+                _ => {}
+            }
+            Some(match Message::try_from(msg) {
+                Ok(mut msg) => {
+                    msg.addr = links.resolve(msg.addr).await;
+                    event!(tracing::Level::TRACE, ?msg, "monitor message");
+                    Ok(Event::Addr(msg))
+                }
+                Err(e) => {
+                    event!(tracing::Level::DEBUG, error = %e, "failed to decode message");
+                    stats.on_decode_error();
+                    Err(e)
+                }
+            })
+        }
+    })
 }
 
 /// Handles to get the current local addresses and their changes.
+///
+/// The monitor is subscribed when the connection is created, so changes made
+/// while the current addresses are being retrieved are buffered rather than
+/// lost.
 pub struct Handle {
     pub addresses: Addresses,
     pub monitor: Monitor,
 }
 
+impl Handle {
+    /// Streams the current local addresses as new addresses, followed by the
+    /// monitor messages including those buffered during the retrieval.
+    ///
+    /// Changes during the retrieval may be seen twice, e.g. an address added
+    /// meanwhile is both retrieved and then reported by the monitor.
+    pub fn stream(self) -> impl Stream<Item = Message> {
+        self.try_stream().filter_map(|item| async { item.ok() })
+    }
+
+    /// Like [`Handle::stream`], along with the errors from
+    /// [`Addresses::try_stream`] and [`Monitor::try_stream`].
+    pub fn try_stream(self) -> impl Stream<Item = Result<Message>> {
+        let current = self.addresses.try_stream();
+        current
+            .map(|item| item.map(|addr| Message::new(addr, true)))
+            .chain(self.monitor.try_stream())
+    }
+
+    /// Streams the current addresses matching `f` as new addresses, followed
+    /// by the monitor messages of matching addresses.
+    ///
+    /// ```no_run
+    /// # async fn run(handle: ip_roam::Handle) {
+    /// use futures::StreamExt;
+    ///
+    /// let s = handle.watch(|a| a.if_name() == Some("wlan0") && a.addr().octets()[0] == 10);
+    /// let mut s = std::pin::pin!(s);
+    /// while let Some(msg) = s.next().await {
+    ///     println!("{}", msg);
+    /// }
+    /// # }
+    /// ```
+    pub fn watch<F>(self, mut f: F) -> impl Stream<Item = Message>
+    where
+        F: FnMut(&Address) -> bool,
+    {
+        self.stream()
+            .filter(move |msg| futures::future::ready(f(msg.addr())))
+    }
+
+    /// Streams the current local addresses as [`Event::Addr`], followed by
+    /// the monitor events of all the subscribed groups in order.
+    pub fn events(self) -> impl Stream<Item = Event> {
+        self.try_events().filter_map(|item| async { item.ok() })
+    }
+
+    /// Like [`Handle::events`], along with the errors from
+    /// [`Addresses::try_stream`] and [`Monitor::try_events`].
+    pub fn try_events(self) -> impl Stream<Item = Result<Event>> {
+        self.addresses
+            .try_stream()
+            .map(|item| item.map(|addr| Event::Addr(Message::new(addr, true))))
+            .chain(self.monitor.try_events())
+    }
+
+    /// Waits until the interface has a global address, checking the current
+    /// addresses first and then the monitor messages.
+    ///
+    /// Monitor messages consumed while waiting are not seen by later streams.
+    /// Fails if the current addresses can't be retrieved, or with `TimedOut`
+    /// if no such address shows up within `timeout`.
+    pub async fn wait_for_addr(&mut self, if_name: &str, timeout: Duration) -> Result<Address> {
+        let found = async {
+            let usable = |a: &Address| a.is_global() && a.if_name() == Some(if_name);
+            let dump = |addresses: Addresses| async move {
+                let mut s = pin!(addresses.try_stream());
+                while let Some(addr) = s.next().await {
+                    let addr = addr?;
+                    if usable(&addr) {
+                        return Ok(Some(addr));
+                    }
+                }
+                Ok::<_, Error>(None)
+            };
+            if let Some(addr) = dump(self.addresses.clone()).await? {
+                return Ok(addr);
+            }
+            let links = self.monitor.links.clone();
+            let stats = self.monitor.stats.clone();
+            let mut s = pin!(decode(&mut self.monitor.messages, links, stats));
+            while let Some(event) = s.next().await {
+                match event {
+                    Ok(Event::Addr(msg)) if msg.is_new() && usable(&msg.addr) => {
+                        return Ok(msg.addr);
+                    }
+                    Ok(Event::Resync) => {
+                        if let Some(addr) = dump(self.addresses.clone()).await? {
+                            return Ok(addr);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Err(Error::from(ErrorKind::UnexpectedEof))
+        };
+        match select(pin!(found), Delay::new(timeout)).await {
+            Either::Left((r, _)) => r,
+            Either::Right(_) => Err(Error::from(ErrorKind::TimedOut)),
+        }
+    }
+}
+
+impl Handle {
+    /// Gets the IPv4 default gateway with the lowest metric in the main
+    /// routing table.
+    pub async fn default_gateway_v4(&self) -> Result<Option<RouteEvent>> {
+        self.default_gateway(IpVersion::V4).await
+    }
+
+    /// Gets the IPv6 default gateway with the lowest metric in the main
+    /// routing table.
+    pub async fn default_gateway_v6(&self) -> Result<Option<RouteEvent>> {
+        self.default_gateway(IpVersion::V6).await
+    }
+
+    async fn default_gateway(&self, version: IpVersion) -> Result<Option<RouteEvent>> {
+        let handle = &self.addresses.handle;
+        let mut routes = pin!(RouteHandle::new(handle.clone()).get(version).execute());
+        let mut best: Option<RouteEvent> = None;
+        while let Some(rm) = routes.try_next().await.map_err(rt_error)? {
+            let route = RouteEvent::new(&rm, true);
+            if route.is_default_gateway()
+                && !matches!(&best, Some(b) if b.priority() <= route.priority())
+            {
+                best = Some(route);
+            }
+        }
+        Ok(match best {
+            Some(route) => Some(self.addresses.links.resolve_route(route).await),
+            None => None,
+        })
+    }
+}
+
+/// A set of netlink multicast groups to subscribe to, combined with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Groups(u32);
+
+impl Groups {
+    /// IPv4 address changes, yielded as [`Event::Addr`].
+    pub const IPV4_IFADDR: Groups = Groups(RTMGRP_IPV4_IFADDR);
+    /// Link changes, yielded as [`Event::Link`]. The links are dumped once the
+    /// connection is driven, so that [`LinkEvent::changes`] are known.
+    pub const LINK: Groups = Groups(RTMGRP_LINK);
+    /// IPv4 route changes, yielded as [`Event::Route`].
+    pub const IPV4_ROUTE: Groups = Groups(RTMGRP_IPV4_ROUTE);
+    /// IPv6 route changes, yielded as [`Event::Route`].
+    pub const IPV6_ROUTE: Groups = Groups(RTMGRP_IPV6_ROUTE);
+    /// Neighbour cache changes, yielded as [`Event::Neigh`].
+    pub const NEIGH: Groups = Groups(RTMGRP_NEIGH);
+
+    /// Creates groups from a `RTMGRP_*` bitmask, e.g. from [`groups`].
+    ///
+    /// Messages of kinds not modeled by [`Event`], e.g. from
+    /// `RTMGRP_IPV6_IFADDR` or `RTMGRP_TC`, show up as decode errors in
+    /// [`Monitor::try_stream`] and are skipped by the other streams.
+    pub const fn from_bits(bits: u32) -> Groups {
+        Groups(bits)
+    }
+
+    /// Gets the `RTMGRP_*` bitmask.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Checks whether all groups in `other` are included.
+    pub const fn contains(self, other: Groups) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Groups {
+    type Output = Groups;
+
+    fn bitor(self, rhs: Groups) -> Groups {
+        Groups(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Groups {
+    fn bitor_assign(&mut self, rhs: Groups) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// The future driving the netlink socket.
+#[must_use = "futures do nothing unless polled"]
+pub struct Driver {
+    conn: RtConnection<RtnlMessage>,
+    relay: Relay,
+}
+
+impl Driver {
+    /// Gets the netlink socket, e.g. to enlarge its receive buffer with
+    /// `socket_mut().socket_mut().set_rx_buf_sz(..)` against overruns.
+    pub fn socket_mut(&mut self) -> &mut TokioSocket {
+        self.conn.socket_mut()
+    }
+}
+
+impl Future for Driver {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = &mut *self;
+        let r = Pin::new(&mut this.conn).poll(cx);
+        this.relay.poll(cx);
+        if r.is_ready() {
+            this.relay.close();
+        }
+        r
+    }
+}
+
 /// A pending connection to the netlink socket.
 pub struct Connection {
-    pub conn: RtConnection<RtnlMessage>,
+    pub conn: Driver,
     /// The `conn` future must be spawned before the `handle` could work.
     pub handle: Handle,
 }
 
 impl Connection {
-    /// Creates a pending connection to the netlink socket.
+    /// Creates a pending connection to the netlink socket, monitoring IPv4
+    /// addresses.
     pub fn new() -> Result<Self> {
+        Self::builder().build()
+    }
+
+    /// Creates a pending connection to the netlink socket, monitoring the
+    /// given multicast groups. Their messages are yielded by
+    /// [`Monitor::events`].
+    pub fn new_with_groups(groups: Groups) -> Result<Self> {
+        Self::builder().groups(groups).build()
+    }
+
+    /// Streams the events like [`Handle::events`], driving the connection
+    /// along the way so `conn` needn't be spawned.
+    pub fn events(self) -> impl Stream<Item = Event> {
+        self.try_events().filter_map(|item| async { item.ok() })
+    }
+
+    /// Like [`Connection::events`], along with the errors like
+    /// [`Handle::try_events`].
+    pub fn try_events(self) -> impl Stream<Item = Result<Event>> {
+        let driver = self.conn.into_stream().map(|_| None);
+        let events = self.handle.try_events().map(Some);
+        futures::stream::select(events, driver).filter_map(futures::future::ready)
+    }
+
+    /// Creates a builder to configure the connection.
+    pub fn builder() -> ConnectionBuilder {
+        ConnectionBuilder {
+            groups: Groups::IPV4_IFADDR,
+            capacity: None,
+            stats: None,
+        }
+    }
+}
+
+/// A builder of [`Connection`]s.
+#[derive(Debug, Clone)]
+pub struct ConnectionBuilder {
+    groups: Groups,
+    capacity: Option<usize>,
+    stats: Option<Stats>,
+}
+
+impl ConnectionBuilder {
+    /// Sets the multicast groups to monitor, which are
+    /// [`Groups::IPV4_IFADDR`] by default.
+    pub fn groups(mut self, groups: Groups) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Bounds the monitor messages buffered for a slow consumer, which are
+    /// unbounded by default. On overflow, the oldest messages are dropped and
+    /// an [`Event::Resync`] is yielded in their place.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Collects the counters of the connection into `stats`, which could be
+    /// cloned beforehand to be read while the connection is in use.
+    pub fn stats(mut self, stats: Stats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Creates the pending connection.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
+    pub fn build(self) -> Result<Connection> {
+        let groups = self.groups;
         let (mut conn, handle, messages) = new_connection()?;
         conn.socket_mut()
             .socket_mut()
-            .bind(&SocketAddr::new(0, RTMGRP_IPV4_IFADDR))?;
+            .bind(&SocketAddr::new(0, (groups | Groups::LINK).bits()))?;
+        event!(tracing::Level::DEBUG, "netlink socket bound");
+        let stats = self.stats.unwrap_or_default();
+        let links = Links::new(handle.clone());
+        let (relay, messages) = queue::queue(
+            messages,
+            links.clone(),
+            groups,
+            self.capacity,
+            stats.clone(),
+        );
         Ok(Connection {
-            conn,
+            conn: Driver { conn, relay },
             handle: Handle {
-                addresses: Addresses { handle },
-                monitor: Monitor { messages },
+                addresses: Addresses {
+                    handle,
+                    links: links.clone(),
+                    stats: stats.clone(),
+                },
+                monitor: Monitor {
+                    messages,
+                    links,
+                    stats,
+                },
             },
         })
     }
@@ -179,8 +842,70 @@ impl Connection {
 
 #[cfg(test)]
 mod tests {
-    use super::Connection;
-    use futures::stream::StreamExt;
+    use super::{decode, queue, Address, Connection, Event, Groups, Stats};
+    use futures::{channel::mpsc::unbounded, task::noop_waker, StreamExt};
+    use netlink_packet_core::{NetlinkHeader, NetlinkMessage, NetlinkPayload};
+    use netlink_packet_route::{rtnl::address::Nla, AddressMessage};
+    use netlink_proto::sys::{AsyncSocket, SocketAddr};
+    use std::io::ErrorKind;
+    use std::net::Ipv4Addr;
+    use std::task::Context;
+    use std::time::Duration;
+
+    #[cfg(feature = "raw")]
+    #[test]
+    fn raw_message() {
+        use super::{Message, RtnlMessage};
+
+        let mut am = AddressMessage::default();
+        am.nlas.push(Nla::Address(vec![10, 0, 0, 2]));
+        am.nlas.push(Nla::Broadcast(vec![10, 0, 0, 255]));
+        let msg = Message::try_from(RtnlMessage::NewAddress(am.clone())).unwrap();
+        assert_eq!(msg.raw(), Some(&am));
+    }
+
+    #[test]
+    fn identity() {
+        use netlink_packet_route::constants::IFA_F_SECONDARY;
+        use std::collections::HashSet;
+
+        let mut am = AddressMessage::default();
+        am.header.index = 2;
+        am.header.prefix_len = 24;
+        am.header.flags = IFA_F_SECONDARY as u8;
+        am.nlas.push(Nla::Address(vec![10, 0, 0, 2]));
+        let secondary = Address::try_from(am.clone()).unwrap();
+        // Promoted to primary once the primary address is deleted.
+        am.header.flags = 0;
+        am.nlas.push(Nla::Label("eth0".into()));
+        let primary = Address::try_from(am.clone()).unwrap();
+        assert_eq!(secondary, primary);
+        let mut set = HashSet::from([secondary]);
+        assert!(!set.insert(primary.clone()));
+        assert!(set.remove(&primary) && set.is_empty());
+
+        am.header.prefix_len = 16;
+        assert_ne!(Address::try_from(am).unwrap(), primary);
+    }
+
+    #[test]
+    fn parse_without_label() {
+        let mut am = AddressMessage::default();
+        am.header.index = 3;
+        am.nlas.push(Nla::Address(vec![10, 0, 0, 2]));
+        let a = Address::try_from(am.clone()).unwrap();
+        assert_eq!(a.addr(), &Ipv4Addr::new(10, 0, 0, 2));
+        assert_eq!(a.label(), None);
+        assert_eq!(a.index(), 3);
+
+        am.nlas.push(Nla::Local(vec![10, 0, 0, 1]));
+        am.nlas.push(Nla::Label("ppp0".into()));
+        let a = Address::try_from(am).unwrap();
+        assert_eq!(a.addr(), &Ipv4Addr::new(10, 0, 0, 1));
+        assert_eq!(a.label(), Some("ppp0"));
+
+        assert!(Address::try_from(AddressMessage::default()).is_err());
+    }
 
     #[tokio::test]
     async fn has_loopback() {
@@ -191,4 +916,150 @@ mod tests {
         assert!(r);
         rt.abort();
     }
+
+    #[tokio::test]
+    async fn loopback_if_name() {
+        let c = Connection::new().unwrap();
+        let rt = tokio::spawn(c.conn);
+        let r: Vec<_> = c.handle.addresses.stream().collect().await;
+        let lo = r.iter().find(|a| a.addr().is_loopback()).unwrap();
+        assert_eq!(lo.if_name(), Some("lo"));
+        rt.abort();
+    }
+
+    #[tokio::test]
+    async fn dump_without_errors() {
+        let c = Connection::new().unwrap();
+        let rt = tokio::spawn(c.conn);
+        let r: Vec<_> = c.handle.addresses.try_stream().collect().await;
+        assert!(!r.is_empty());
+        assert!(r.iter().all(|a| a.is_ok()));
+        rt.abort();
+    }
+
+    #[tokio::test]
+    async fn handle_stream_starts_with_dump() {
+        let c = Connection::new().unwrap();
+        let rt = tokio::spawn(c.conn);
+        let mut s = std::pin::pin!(c.handle.try_stream());
+        let first = s.next().await.unwrap().unwrap();
+        assert!(first.is_new());
+        rt.abort();
+    }
+
+    #[tokio::test]
+    async fn events_without_spawning() {
+        let c = Connection::new_with_groups(Groups::IPV4_IFADDR | Groups::NEIGH).unwrap();
+        let mut s = std::pin::pin!(c.events());
+        let first = s.next().await.unwrap();
+        assert!(matches!(first, Event::Addr(msg) if msg.is_new()));
+
+        let c = Connection::new().unwrap();
+        let mut s = std::pin::pin!(c.try_events());
+        let first = s.next().await.unwrap();
+        assert!(matches!(first, Ok(Event::Addr(msg)) if msg.is_new()));
+    }
+
+    #[tokio::test]
+    async fn watch_loopback() {
+        let c = Connection::new().unwrap();
+        let rt = tokio::spawn(c.conn);
+        let mut s = std::pin::pin!(c.handle.watch(|a| a.addr().is_loopback()));
+        let first = s.next().await.unwrap();
+        assert!(first.is_new() && first.addr().addr().is_loopback());
+        rt.abort();
+    }
+
+    #[tokio::test]
+    async fn wait_for_loopback() {
+        let mut c = Connection::new().unwrap();
+        let rt = tokio::spawn(c.conn);
+        let r = c.handle.wait_for_addr("lo", Duration::from_secs(1)).await;
+        // Loopback addresses have the host scope.
+        assert_eq!(r.unwrap_err().kind(), ErrorKind::TimedOut);
+        rt.abort();
+    }
+
+    #[tokio::test]
+    async fn default_gateway() {
+        let c = Connection::new().unwrap();
+        let rt = tokio::spawn(c.conn);
+        // There may be no default route in the sandbox, so only check the
+        // query succeeds.
+        let r = c.handle.default_gateway_v4().await.unwrap();
+        if let Some(route) = r {
+            assert!(route.is_default_gateway());
+        }
+        rt.abort();
+    }
+
+    #[tokio::test]
+    async fn overrun_resync() {
+        let c = Connection::new().unwrap();
+        let rt = tokio::spawn(c.conn);
+        let links = c.handle.monitor.links;
+        let msg = NetlinkMessage::new(NetlinkHeader::default(), NetlinkPayload::Overrun(vec![]));
+        let (tx, rx) = unbounded();
+        tx.unbounded_send((msg, SocketAddr::new(0, 0))).unwrap();
+        drop(tx);
+        let stats = Stats::new();
+        let (mut relay, rx) =
+            queue::queue(rx, links.clone(), Groups::IPV4_IFADDR, None, stats.clone());
+        relay.poll(&mut Context::from_waker(&noop_waker()));
+        // Counted before being consumed.
+        assert_eq!(stats.overruns(), 1);
+        assert_eq!(stats.received(), 0);
+        let r: Vec<_> = decode(rx, links, stats.clone()).collect().await;
+        assert!(matches!(r[..], [Ok(Event::Resync)]));
+        rt.abort();
+    }
+
+    #[tokio::test]
+    async fn enlarge_rx_buffer() {
+        let mut c = Connection::new().unwrap();
+        let socket = c.conn.socket_mut().socket_mut();
+        socket.set_rx_buf_sz(1 << 20).unwrap();
+        assert!(socket.get_rx_buf_sz().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn idle_timeout_on_any_event() {
+        use netlink_packet_route::{RouteMessage, RtnlMessage};
+
+        let c = Connection::new().unwrap();
+        let rt = tokio::spawn(c.conn);
+        let links = c.handle.monitor.links;
+        let (tx, rx) = unbounded();
+        let msg = NetlinkMessage::new(
+            NetlinkHeader::default(),
+            NetlinkPayload::InnerMessage(RtnlMessage::NewRoute(RouteMessage::default())),
+        );
+        tx.unbounded_send((msg, SocketAddr::new(0, 0))).unwrap();
+        let stats = Stats::new();
+        let (mut relay, rx) =
+            queue::queue(rx, links.clone(), Groups::IPV4_ROUTE, None, stats.clone());
+        relay.poll(&mut Context::from_waker(&noop_waker()));
+        let monitor = super::Monitor {
+            messages: rx,
+            links,
+            stats,
+        };
+        let s = monitor.stream_with_idle_timeout(Duration::from_millis(50));
+        let r: Vec<_> = s.take(2).collect().await;
+        assert!(matches!(r[0], Ok(Event::Route(_))));
+        assert_eq!(r[1].as_ref().unwrap_err().kind(), ErrorKind::TimedOut);
+        rt.abort();
+    }
+
+    #[tokio::test]
+    async fn dump_stats() {
+        let stats = Stats::new();
+        let c = Connection::builder().stats(stats.clone()).build().unwrap();
+        let rt = tokio::spawn(c.conn);
+        assert_eq!(stats.last_dump_duration(), None);
+        c.handle.addresses.stream().for_each(|_| async {}).await;
+        assert_eq!(stats.dumps(), 1);
+        assert!(stats.last_dump_duration().is_some());
+        rt.abort();
+    }
 }
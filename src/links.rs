@@ -0,0 +1,137 @@
+use crate::{Address, LinkEvent, NeighEvent, RouteEvent};
+use futures::stream::TryStreamExt;
+use rtnetlink::{Handle as RtHandle, LinkHandle};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A cache of the links by index, shared by the handles.
+#[derive(Debug, Clone)]
+pub(crate) struct Links {
+    handle: RtHandle,
+    links: Arc<Mutex<HashMap<u32, LinkEvent>>>,
+    dumped: Arc<AtomicBool>,
+}
+
+impl Links {
+    pub(crate) fn new(handle: RtHandle) -> Self {
+        Links {
+            handle,
+            links: Arc::default(),
+            dumped: Arc::default(),
+        }
+    }
+
+    /// Applies a link event, returning the previous state of the link.
+    pub(crate) fn update(&self, link: &LinkEvent) -> Option<LinkEvent> {
+        let mut links = self.links.lock().unwrap();
+        if link.is_new() {
+            event!(
+                tracing::Level::TRACE,
+                index = link.index(),
+                name = link.name(),
+                "link updated"
+            );
+            links.insert(link.index(), link.clone())
+        } else {
+            event!(tracing::Level::TRACE, index = link.index(), "link removed");
+            links.remove(&link.index())
+        }
+    }
+
+    /// Fills the cache with all links unless it's been done, returning whether
+    /// it's done this time. This is done once on the first miss, or before the
+    /// first link event if they are monitored.
+    pub(crate) async fn dump_once(&self) -> bool {
+        if self.dumped.swap(true, Ordering::Relaxed) {
+            return false;
+        }
+        self.dump().await;
+        true
+    }
+
+    async fn dump(&self) {
+        let mut links = LinkHandle::new(self.handle.clone()).get().execute();
+        loop {
+            match links.try_next().await {
+                Ok(Some(lm)) => {
+                    self.update(&LinkEvent::new(&lm, true));
+                }
+                Ok(None) => break,
+                Err(_e) => {
+                    event!(tracing::Level::DEBUG, error = %_e, "failed to dump links");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn cached(&self, index: u32) -> Option<String> {
+        let links = self.links.lock().unwrap();
+        links.get(&index)?.name().map(str::to_owned)
+    }
+
+    pub(crate) async fn name(&self, index: u32) -> Option<String> {
+        if let Some(name) = self.cached(index) {
+            return Some(name);
+        }
+        if self.dump_once().await {
+            if let Some(name) = self.cached(index) {
+                return Some(name);
+            }
+        }
+        let mut links = LinkHandle::new(self.handle.clone())
+            .get()
+            .match_index(index)
+            .execute();
+        let link = match links.try_next().await {
+            Ok(Some(lm)) => LinkEvent::new(&lm, true),
+            Ok(None) => return None,
+            Err(_e) => {
+                event!(tracing::Level::DEBUG, index, error = %_e, "failed to get link");
+                return None;
+            }
+        };
+        self.update(&link);
+        link.name().map(str::to_owned)
+    }
+
+    pub(crate) async fn resolve(&self, mut addr: Address) -> Address {
+        addr.if_name = self.name(addr.index).await;
+        addr
+    }
+
+    pub(crate) async fn resolve_route(&self, mut route: RouteEvent) -> RouteEvent {
+        if let Some(oif) = route.oif() {
+            route.if_name = self.name(oif).await;
+        }
+        route
+    }
+
+    pub(crate) async fn resolve_neigh(&self, mut neigh: NeighEvent) -> NeighEvent {
+        neigh.if_name = self.name(neigh.index()).await;
+        neigh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Links;
+    use rtnetlink::new_connection;
+
+    #[tokio::test]
+    async fn dump_on_first_miss() {
+        let (conn, handle, _) = new_connection().unwrap();
+        let rt = tokio::spawn(conn);
+        let links = Links::new(handle);
+        // The loopback interface always has index 1.
+        assert_eq!(links.name(1).await.as_deref(), Some("lo"));
+        assert!(links
+            .links
+            .lock()
+            .unwrap()
+            .values()
+            .any(|l| l.name() == Some("lo")));
+        rt.abort();
+    }
+}
@@ -0,0 +1,108 @@
+//! A fallback backend polling `getifaddrs(3)`, for environments where netlink
+//! is unavailable or for debugging.
+
+use crate::{Address, Message};
+use futures::{stream, Stream, StreamExt};
+use netlink_packet_route::constants::{RT_SCOPE_HOST, RT_SCOPE_LINK};
+use nix::{ifaddrs::getifaddrs, net::if_::if_nametoindex};
+use std::collections::BTreeSet;
+use std::io::Result;
+use std::net::Ipv4Addr;
+
+/// The addresses keyed by their identity, like the netlink backend.
+type Snapshot = BTreeSet<Address>;
+
+/// Gets the current local addresses.
+pub fn addresses() -> Result<Vec<Address>> {
+    Ok(snapshot()?.into_iter().collect())
+}
+
+fn snapshot() -> Result<Snapshot> {
+    let mut r = BTreeSet::new();
+    for ifa in getifaddrs()? {
+        let Some(addr) = ifa.address.as_ref().and_then(|a| a.as_sockaddr_in()) else {
+            continue;
+        };
+        let addr = Ipv4Addr::from(addr.ip());
+        let prefix_len = ifa
+            .netmask
+            .as_ref()
+            .and_then(|m| m.as_sockaddr_in())
+            .map_or(32, |m| m.ip().count_ones() as u8);
+        // Aliases like `eth0:1` are reported as the interface name.
+        let label = ifa.interface_name;
+        let if_name = label.split(':').next().unwrap_or(&label).to_owned();
+        let index = if_nametoindex(if_name.as_str()).unwrap_or(0);
+        let mut a = Address::new(addr, prefix_len, Some(label), index);
+        a.if_name = Some(if_name);
+        if addr.is_loopback() {
+            a.scope = RT_SCOPE_HOST;
+        } else if addr.is_link_local() {
+            a.scope = RT_SCOPE_LINK;
+        }
+        r.insert(a);
+    }
+    Ok(r)
+}
+
+fn diff(old: &Snapshot, new: &Snapshot) -> Vec<Message> {
+    let deleted = old.difference(new).map(|a| Message::new(a.clone(), false));
+    let added = new.difference(old).map(|a| Message::new(a.clone(), true));
+    deleted.chain(added).collect()
+}
+
+/// Polls the local addresses on every item of `ticks`, yielding the changes
+/// since the previous poll as messages, like [`Monitor::stream`].
+///
+/// `ticks` is usually an interval timer of the runtime in use. The first poll
+/// happens immediately and serves as the baseline. Failed polls are skipped.
+///
+/// [`Monitor::stream`]: crate::Monitor::stream
+pub fn monitor<S: Stream>(ticks: S) -> impl Stream<Item = Message> {
+    let mut last = snapshot().unwrap_or_default();
+    ticks
+        .filter_map(move |_| {
+            let msgs = snapshot().ok().map(|cur| {
+                let msgs = diff(&last, &cur);
+                last = cur;
+                stream::iter(msgs)
+            });
+            async { msgs }
+        })
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{addresses, diff, Snapshot};
+    use crate::Address;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn diff_snapshots() {
+        let a = Ipv4Addr::new(192, 168, 1, 2);
+        let b = Ipv4Addr::new(10, 0, 0, 2);
+        let old: Snapshot = [Address::new(a, 24, None, 2)].into();
+        let new: Snapshot = [Address::new(b, 8, None, 2)].into();
+        let r = diff(&old, &new);
+        assert_eq!(r.len(), 2);
+        assert!(!r[0].is_new() && r[0].addr().addr() == &a);
+        assert!(r[1].is_new() && r[1].addr().addr() == &b);
+        assert!(diff(&new, &new).is_empty());
+
+        // A prefix change is a different address.
+        let new: Snapshot = [Address::new(a, 16, None, 2)].into();
+        let r = diff(&old, &new);
+        assert_eq!(r.len(), 2);
+        assert!(!r[0].is_new() && r[0].addr().prefix_len() == 24);
+        assert!(r[1].is_new() && r[1].addr().prefix_len() == 16);
+    }
+
+    #[test]
+    fn has_loopback() {
+        let r = addresses().unwrap();
+        assert!(r
+            .iter()
+            .any(|a| a.addr().is_loopback() && a.if_name() == Some("lo")));
+    }
+}
@@ -0,0 +1,336 @@
+use crate::{Groups, LinkEvent, Links, Stats};
+use futures::{
+    channel::mpsc::UnboundedReceiver,
+    future::{BoxFuture, FutureExt},
+    Stream, StreamExt,
+};
+use netlink_packet_core::{NetlinkMessage, NetlinkPayload};
+use netlink_packet_route::{RtnlMessage, RtnlMessage::*};
+use netlink_proto::sys::SocketAddr;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// A monitor message as received from the socket.
+pub(crate) type Raw = (NetlinkMessage<RtnlMessage>, SocketAddr);
+
+/// A monitor message relayed to the consumer.
+#[derive(Debug)]
+pub(crate) enum Item {
+    /// A message to be decoded by the consumer.
+    Message(NetlinkMessage<RtnlMessage>),
+    /// A link message, already applied to the link cache.
+    Link(LinkEvent),
+    /// Messages were dropped by the socket or the queue.
+    Overrun,
+}
+
+#[derive(Debug)]
+struct Shared {
+    items: VecDeque<Item>,
+    capacity: Option<usize>,
+    overflowed: bool,
+    closed: bool,
+    waker: Option<Waker>,
+    stats: Stats,
+}
+
+impl Shared {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn push(&mut self, item: Item) {
+        if matches!(self.capacity, Some(capacity) if self.items.len() >= capacity) {
+            self.items.pop_front();
+            self.stats.on_dropped();
+            if !self.overflowed {
+                event!(tracing::Level::WARN, "monitor queue overflowed");
+                self.stats.on_overrun();
+                self.overflowed = true;
+            }
+        }
+        self.items.push_back(item);
+    }
+}
+
+/// Moves the messages from the socket into the queue, applying link messages
+/// to the link cache on the way so it stays fresh even if the monitor isn't
+/// consumed. Once the receiver is dropped, the messages are only applied to
+/// the cache rather than queued. It's polled by the connection driver.
+pub(crate) struct Relay {
+    rx: UnboundedReceiver<Raw>,
+    links: Links,
+    groups: Groups,
+    shared: Arc<Mutex<Shared>>,
+    /// The pending dump of links, which the link events wait for so their
+    /// changes are known.
+    dump: Option<BoxFuture<'static, ()>>,
+}
+
+impl Relay {
+    pub(crate) fn poll(&mut self, cx: &mut Context) {
+        if let Some(dump) = &mut self.dump {
+            if dump.poll_unpin(cx).is_pending() {
+                return;
+            }
+            self.dump = None;
+        }
+        // Nobody is left to consume the queue.
+        let orphaned = Arc::strong_count(&self.shared) == 1;
+        let mut shared = self.shared.lock().unwrap();
+        if shared.closed {
+            return;
+        }
+        if orphaned {
+            shared.items.clear();
+        }
+        loop {
+            match self.rx.poll_next_unpin(cx) {
+                Poll::Ready(Some((msg, _))) => {
+                    if !matches!(msg.payload, NetlinkPayload::Overrun(_)) {
+                        shared.stats.on_received();
+                    }
+                    match self.relay(msg, &shared.stats) {
+                        Some(item) if !orphaned => shared.push(item),
+                        _ => {}
+                    }
+                }
+                Poll::Ready(None) => {
+                    shared.closed = true;
+                    break;
+                }
+                Poll::Pending => break,
+            }
+        }
+        shared.stats.set_queued(shared.items.len());
+        shared.wake();
+    }
+
+    fn relay(&self, msg: NetlinkMessage<RtnlMessage>, stats: &Stats) -> Option<Item> {
+        match &msg.payload {
+            NetlinkPayload::InnerMessage(m @ (NewLink(lm) | DelLink(lm))) => {
+                let mut link = LinkEvent::new(lm, matches!(m, NewLink(_)));
+                if let Some(old) = self.links.update(&link) {
+                    link.set_previous(&old);
+                }
+                // Links are always monitored to keep the cache fresh, but only
+                // yielded if asked for.
+                self.groups
+                    .contains(Groups::LINK)
+                    .then_some(Item::Link(link))
+            }
+            NetlinkPayload::Overrun(_) => {
+                stats.on_overrun();
+                Some(Item::Overrun)
+            }
+            _ => Some(Item::Message(msg)),
+        }
+    }
+
+    pub(crate) fn close(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.closed = true;
+        shared.wake();
+    }
+}
+
+/// The receiving end of the queue, yielding an overrun in place of the
+/// dropped messages.
+#[derive(Debug)]
+pub(crate) struct Receiver {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Stream for Receiver {
+    type Item = Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Item>> {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.overflowed {
+            shared.overflowed = false;
+            return Poll::Ready(Some(Item::Overrun));
+        }
+        if let Some(item) = shared.items.pop_front() {
+            shared.stats.set_queued(shared.items.len());
+            return Poll::Ready(Some(item));
+        }
+        if shared.closed {
+            return Poll::Ready(None);
+        }
+        shared.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Creates the queue of monitor messages, which is unbounded if `capacity` is
+/// `None` and drops the oldest messages on overflow otherwise.
+pub(crate) fn queue(
+    rx: UnboundedReceiver<Raw>,
+    links: Links,
+    groups: Groups,
+    capacity: Option<usize>,
+    stats: Stats,
+) -> (Relay, Receiver) {
+    let shared = Arc::new(Mutex::new(Shared {
+        items: VecDeque::with_capacity(capacity.unwrap_or(0)),
+        capacity: capacity.map(|c| c.max(1)),
+        overflowed: false,
+        closed: false,
+        waker: None,
+        stats,
+    }));
+    let dump = groups.contains(Groups::LINK).then(|| {
+        let links = links.clone();
+        async move {
+            links.dump_once().await;
+        }
+        .boxed()
+    });
+    (
+        Relay {
+            rx,
+            links,
+            groups,
+            shared: shared.clone(),
+            dump,
+        },
+        Receiver { shared },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{queue, Item, Raw};
+    use crate::{Groups, LinkChange, Links, Stats};
+    use futures::{
+        channel::mpsc::unbounded,
+        future::{poll_fn, select, Either},
+        task::noop_waker,
+        StreamExt,
+    };
+    use netlink_packet_core::{NetlinkHeader, NetlinkMessage, NetlinkPayload};
+    use netlink_packet_route::{rtnl::link::nlas::Nla, LinkMessage, RtnlMessage};
+    use netlink_proto::sys::SocketAddr;
+    use rtnetlink::new_connection;
+    use std::pin::pin;
+    use std::task::{Context, Poll};
+
+    fn raw(payload: NetlinkPayload<RtnlMessage>, seq: u32) -> Raw {
+        let mut hdr = NetlinkHeader::default();
+        hdr.sequence_number = seq;
+        (NetlinkMessage::new(hdr, payload), SocketAddr::new(0, 0))
+    }
+
+    fn link(index: u32, name: &str) -> Raw {
+        let mut lm = LinkMessage::default();
+        lm.header.index = index;
+        lm.nlas.push(Nla::IfName(name.into()));
+        lm.nlas.push(Nla::Mtu(1234));
+        raw(NetlinkPayload::InnerMessage(RtnlMessage::NewLink(lm)), 0)
+    }
+
+    #[tokio::test]
+    async fn drop_oldest() {
+        let (_, handle, _) = new_connection().unwrap();
+        let (tx, rx) = unbounded();
+        let stats = Stats::new();
+        let links = Links::new(handle);
+        let (mut relay, rx) = queue(rx, links, Groups::IPV4_IFADDR, Some(2), stats.clone());
+        for seq in 1..=3 {
+            tx.unbounded_send(raw(NetlinkPayload::Noop, seq)).unwrap();
+        }
+        drop(tx);
+        relay.poll(&mut Context::from_waker(&noop_waker()));
+        assert_eq!(stats.dropped(), 1);
+        assert_eq!(stats.overruns(), 1);
+        assert_eq!(stats.queued(), 2);
+        let r: Vec<_> = rx.collect().await;
+        assert_eq!(r.len(), 3);
+        assert!(matches!(r[0], Item::Overrun));
+        assert!(matches!(&r[1], Item::Message(m) if m.header.sequence_number == 2));
+        assert!(matches!(&r[2], Item::Message(m) if m.header.sequence_number == 3));
+        assert_eq!(stats.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn links_without_consumer() {
+        let (_, handle, _) = new_connection().unwrap();
+        let (tx, rx) = unbounded();
+        let links = Links::new(handle);
+        let (mut relay, rx) = queue(rx, links.clone(), Groups::IPV4_IFADDR, None, Stats::new());
+        tx.unbounded_send(link(42, "eth0")).unwrap();
+        tx.unbounded_send(link(42, "lan0")).unwrap();
+        drop(tx);
+        relay.poll(&mut Context::from_waker(&noop_waker()));
+        // The cache is updated before anything is consumed, and links aren't
+        // queued unless asked for.
+        assert_eq!(links.name(42).await.as_deref(), Some("lan0"));
+        assert_eq!(rx.count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn changes_of_uncached_link() {
+        let (conn, handle, _) = new_connection().unwrap();
+        let rt = tokio::spawn(conn);
+        let (tx, rx) = unbounded();
+        let links = Links::new(handle);
+        let (mut relay, mut rx) = queue(rx, links, Groups::LINK, None, Stats::new());
+        // The loopback interface always has index 1, and it's not cached yet.
+        tx.unbounded_send(link(1, "lo")).unwrap();
+        let drive = poll_fn(|cx| {
+            relay.poll(cx);
+            Poll::<()>::Pending
+        });
+        let item = match select(pin!(drive), rx.next()).await {
+            Either::Right((item, _)) => item.unwrap(),
+            Either::Left(_) => unreachable!(),
+        };
+        let Item::Link(link) = item else {
+            panic!("unexpected item: {:?}", item);
+        };
+        assert!(link
+            .changes()
+            .iter()
+            .any(|c| matches!(c, LinkChange::Mtu(Some(mtu)) if *mtu != 1234)));
+        rt.abort();
+    }
+
+    #[tokio::test]
+    async fn unbounded_depth() {
+        let (_, handle, _) = new_connection().unwrap();
+        let (tx, rx) = unbounded();
+        let stats = Stats::new();
+        let links = Links::new(handle);
+        let (mut relay, mut rx) = queue(rx, links, Groups::IPV4_IFADDR, None, stats.clone());
+        for seq in 1..=3 {
+            tx.unbounded_send(raw(NetlinkPayload::Noop, seq)).unwrap();
+        }
+        tx.unbounded_send(link(42, "eth0")).unwrap();
+        relay.poll(&mut Context::from_waker(&noop_waker()));
+        // Received before being consumed, including the unqueued link.
+        assert_eq!(stats.received(), 4);
+        assert_eq!(stats.queued(), 3);
+        rx.next().await.unwrap();
+        assert_eq!(stats.queued(), 2);
+    }
+
+    #[tokio::test]
+    async fn dropped_receiver() {
+        let (_, handle, _) = new_connection().unwrap();
+        let (tx, rx) = unbounded();
+        let stats = Stats::new();
+        let links = Links::new(handle);
+        let (mut relay, rx) = queue(rx, links.clone(), Groups::IPV4_IFADDR, None, stats.clone());
+        drop(rx);
+        tx.unbounded_send(raw(NetlinkPayload::Noop, 1)).unwrap();
+        tx.unbounded_send(link(42, "eth0")).unwrap();
+        relay.poll(&mut Context::from_waker(&noop_waker()));
+        assert_eq!(stats.received(), 2);
+        assert_eq!(stats.queued(), 0);
+        assert_eq!(links.name(42).await.as_deref(), Some("eth0"));
+    }
+}
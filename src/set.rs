@@ -0,0 +1,178 @@
+use crate::{Address, Addresses, Message};
+use futures::StreamExt;
+use ipnet::Ipv4Net;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Result;
+
+/// A change between two [`AddressSet`]s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    if_name: String,
+    net: Ipv4Net,
+    new: bool,
+}
+
+impl Change {
+    /// Gets the name of the interface.
+    pub fn if_name(&self) -> &str {
+        &self.if_name
+    }
+
+    /// Gets the address along with its prefix length.
+    pub fn net(&self) -> &Ipv4Net {
+        &self.net
+    }
+
+    /// Checks whether the address is added or removed.
+    pub fn is_new(&self) -> bool {
+        self.new
+    }
+}
+
+/// The addresses of each interface, kept up to date by applying monitor
+/// messages.
+///
+/// Addresses are keyed by the interface name, or by the label if the name
+/// couldn't be resolved. Addresses with neither are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AddressSet {
+    sets: HashMap<String, BTreeSet<Ipv4Net>>,
+}
+
+fn key(addr: &Address) -> Option<&str> {
+    addr.if_name().or_else(|| addr.label())
+}
+
+impl AddressSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the current local addresses, failing if they can't be
+    /// retrieved.
+    pub async fn current(addresses: &Addresses) -> Result<Self> {
+        let mut set = Self::new();
+        let mut s = std::pin::pin!(addresses.clone().try_stream());
+        while let Some(addr) = s.next().await {
+            set.insert(&addr?);
+        }
+        Ok(set)
+    }
+
+    /// Adds an address, returning whether it was absent.
+    pub fn insert(&mut self, addr: &Address) -> bool {
+        match key(addr) {
+            Some(k) => self
+                .sets
+                .entry(k.to_owned())
+                .or_default()
+                .insert(addr.net()),
+            None => false,
+        }
+    }
+
+    /// Removes an address, returning whether it was present.
+    pub fn remove(&mut self, addr: &Address) -> bool {
+        let Some(set) = key(addr).and_then(|k| self.sets.get_mut(k)) else {
+            return false;
+        };
+        let r = set.remove(&addr.net());
+        if set.is_empty() {
+            self.sets.remove(key(addr).unwrap());
+        }
+        r
+    }
+
+    /// Applies a monitor message, returning whether the set changed.
+    pub fn apply(&mut self, msg: &Message) -> bool {
+        if msg.is_new() {
+            self.insert(msg.addr())
+        } else {
+            self.remove(msg.addr())
+        }
+    }
+
+    /// Gets the addresses of an interface.
+    pub fn get(&self, if_name: &str) -> Option<&BTreeSet<Ipv4Net>> {
+        self.sets.get(if_name)
+    }
+
+    /// Iterates over the interfaces and their addresses.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BTreeSet<Ipv4Net>)> {
+        self.sets.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    /// Checks whether there are no addresses.
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    /// Computes the changes turning `self` into `other`, removals first.
+    pub fn diff(&self, other: &AddressSet) -> Vec<Change> {
+        let empty = BTreeSet::new();
+        let mut removed = Vec::new();
+        let mut added = Vec::new();
+        for (if_name, old) in &self.sets {
+            let new = other.sets.get(if_name).unwrap_or(&empty);
+            removed.extend(old.difference(new).map(|&net| Change {
+                if_name: if_name.clone(),
+                net,
+                new: false,
+            }));
+        }
+        for (if_name, new) in &other.sets {
+            let old = self.sets.get(if_name).unwrap_or(&empty);
+            added.extend(new.difference(old).map(|&net| Change {
+                if_name: if_name.clone(),
+                net,
+                new: true,
+            }));
+        }
+        removed.extend(added);
+        removed
+    }
+}
+
+impl<'a> FromIterator<&'a Address> for AddressSet {
+    fn from_iter<I: IntoIterator<Item = &'a Address>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for addr in iter {
+            set.insert(addr);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AddressSet;
+    use crate::{Address, Message};
+    use std::net::Ipv4Addr;
+
+    fn addr(a: [u8; 4], if_name: &str) -> Address {
+        let mut r = Address::new(Ipv4Addr::from(a), 24, None, 2);
+        r.if_name = Some(if_name.to_owned());
+        r
+    }
+
+    #[test]
+    fn apply_and_diff() {
+        let a = addr([192, 168, 1, 2], "wlan0");
+        let b = addr([10, 0, 0, 2], "wlan0");
+        let old: AddressSet = [&a].into_iter().collect();
+        let mut new = old.clone();
+        assert!(new.apply(&Message::new(b.clone(), true)));
+        assert!(!new.apply(&Message::new(b.clone(), true)));
+        assert!(new.apply(&Message::new(a.clone(), false)));
+        assert_eq!(new.get("wlan0").unwrap().len(), 1);
+
+        let d = old.diff(&new);
+        assert_eq!(d.len(), 2);
+        assert!(!d[0].is_new() && d[0].net() == &a.net());
+        assert!(d[1].is_new() && d[1].net() == &b.net() && d[1].if_name() == "wlan0");
+
+        assert!(new.apply(&Message::new(b, false)));
+        assert!(new.is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering::Relaxed};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct Counters {
+    received: AtomicU64,
+    decode_errors: AtomicU64,
+    overruns: AtomicU64,
+    dropped: AtomicU64,
+    queued: AtomicUsize,
+    dumps: AtomicU64,
+    last_dump: AtomicU64,
+}
+
+/// Counters on the health of a connection, shared by its clones.
+///
+/// Pass it to [`ConnectionBuilder::stats`](crate::ConnectionBuilder::stats)
+/// and read it from anywhere, e.g. to tell when the monitor is falling behind.
+#[derive(Debug, Clone, Default)]
+pub struct Stats(Arc<Counters>);
+
+impl Stats {
+    /// Creates zeroed counters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the number of netlink messages received by the monitor, counted by
+    /// the [`Driver`](crate::Driver) whether or not they are consumed yet.
+    pub fn received(&self) -> u64 {
+        self.0.received.load(Relaxed)
+    }
+
+    /// Gets the number of monitor messages that failed to decode.
+    pub fn decode_errors(&self) -> u64 {
+        self.0.decode_errors.load(Relaxed)
+    }
+
+    /// Gets the number of socket buffer overruns reported by the kernel, plus
+    /// the number of times the bounded queue started dropping messages. Each
+    /// is counted by the [`Driver`](crate::Driver) as it happens, and later
+    /// yielded as an [`Event::Resync`](crate::Event::Resync).
+    pub fn overruns(&self) -> u64 {
+        self.0.overruns.load(Relaxed)
+    }
+
+    /// Gets the number of messages dropped from the bounded queue, see
+    /// [`ConnectionBuilder::capacity`](crate::ConnectionBuilder::capacity).
+    pub fn dropped(&self) -> u64 {
+        self.0.dropped.load(Relaxed)
+    }
+
+    /// Gets the number of messages received but not consumed yet, which keeps
+    /// growing if the monitor is falling behind, up to the capacity if any.
+    pub fn queued(&self) -> usize {
+        self.0.queued.load(Relaxed)
+    }
+
+    /// Gets the number of completed address dumps.
+    pub fn dumps(&self) -> u64 {
+        self.0.dumps.load(Relaxed)
+    }
+
+    /// Gets the duration of the last completed address dump.
+    pub fn last_dump_duration(&self) -> Option<Duration> {
+        (self.dumps() > 0).then(|| Duration::from_nanos(self.0.last_dump.load(Relaxed)))
+    }
+
+    pub(crate) fn on_received(&self) {
+        self.0.received.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn on_decode_error(&self) {
+        self.0.decode_errors.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn on_overrun(&self) {
+        self.0.overruns.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn on_dropped(&self) {
+        self.0.dropped.fetch_add(1, Relaxed);
+    }
+
+    pub(crate) fn set_queued(&self, n: usize) {
+        self.0.queued.store(n, Relaxed);
+    }
+
+    pub(crate) fn on_dump(&self, d: Duration) {
+        let nanos = d.as_nanos().try_into().unwrap_or(u64::MAX);
+        self.0.last_dump.store(nanos, Relaxed);
+        self.0.dumps.fetch_add(1, Relaxed);
+    }
+}